@@ -2,7 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 use ff::Field;
-use ironfish_zkp::constants::PUBLIC_KEY_GENERATOR;
+use ironfish_zkp::{constants::PUBLIC_KEY_GENERATOR, errors::DeserializeError};
 use jubjub::SubgroupPoint;
 use rand::thread_rng;
 
@@ -41,12 +41,23 @@ impl EphemeralKeyPair {
         res
     }
 
-    pub fn from_bytes_le(bytes: Vec<u8>) -> Self {
+    pub fn from_bytes_le(bytes: Vec<u8>) -> Result<Self, DeserializeError> {
+        if bytes.len() != 192 {
+            return Err(DeserializeError::UnexpectedLength {
+                expected: 192,
+                found: bytes.len(),
+            });
+        }
+
         let secret_bytes: &[u8; 32] = bytes[0..32].try_into().unwrap();
         let public_bytes: &[u8; 160] = bytes[32..192].try_into().unwrap();
-        let secret = jubjub::Fr::from_bytes(secret_bytes).unwrap();
-        let public = SubgroupPoint::from_bytes_le(public_bytes);
-        Self { secret, public }
+
+        let secret = Option::from(jubjub::Fr::from_bytes(secret_bytes))
+            .ok_or(DeserializeError::InvalidScalar)?;
+        let public = Option::from(SubgroupPoint::from_bytes_le(public_bytes))
+            .ok_or(DeserializeError::InvalidPoint)?;
+
+        Ok(Self { secret, public })
     }
 }
 
@@ -68,4 +79,25 @@ mod test {
         assert_eq!(key_pair.public(), &key_pair.public);
         assert_eq!(key_pair.secret(), &key_pair.secret);
     }
+
+    #[test]
+    fn test_ephemeral_key_pair_round_trip() {
+        let key_pair = EphemeralKeyPair::new();
+
+        let bytes = key_pair.to_bytes_le();
+        let round_tripped = EphemeralKeyPair::from_bytes_le(bytes).unwrap();
+
+        assert_eq!(key_pair.secret(), round_tripped.secret());
+        assert_eq!(key_pair.public(), round_tripped.public());
+    }
+
+    #[test]
+    fn test_ephemeral_key_pair_rejects_truncated_bytes() {
+        let key_pair = EphemeralKeyPair::new();
+
+        let mut bytes = key_pair.to_bytes_le();
+        bytes.truncate(100);
+
+        assert!(EphemeralKeyPair::from_bytes_le(bytes).is_err());
+    }
 }
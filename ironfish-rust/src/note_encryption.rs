@@ -0,0 +1,163 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Encrypts note plaintext to a recipient's diversified transmission key
+//! and decrypts it back out, turning the raw ECDH exchange performed by
+//! [`crate::keys::ephemeral::EphemeralKeyPair`] into a usable encrypted
+//! channel. The key agreement and KDF mirror the Sapling note encryption
+//! scheme: the sender derives the shared secret as `esk · pk_d`, the
+//! recipient as `ivk · epk`, and both sides fold the resulting point and
+//! `epk` through BLAKE2b-256 to reach a symmetric key. A second layer,
+//! keyed by the outgoing viewing key `ovk`, lets the sender recover notes
+//! they sent without retaining `esk` themselves.
+
+use blake2b_simd::Params as Blake2bParams;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Nonce,
+};
+use jubjub::SubgroupPoint;
+
+use crate::{keys::ephemeral::EphemeralKeyPair, note::Note};
+
+const KDF_PERSONALIZATION: &[u8; 16] = b"Zcash_SaplingKDF";
+const OCK_PERSONALIZATION: &[u8; 16] = b"Zcash_Derive_ock";
+
+/// The nonce is fixed because every note is encrypted under a fresh,
+/// single-use symmetric key derived from a fresh `epk`.
+const NOTE_NONCE: &[u8; 12] = &[0u8; 12];
+const OUTGOING_NONCE: &[u8; 12] = &[0u8; 12];
+
+/// A note, encrypted to a recipient's diversified transmission key `pk_d`.
+/// `epk` is carried alongside the ciphertext so the recipient can redo the
+/// key agreement without needing the sender's ephemeral secret. The
+/// `out_ciphertext` lets the sender recover the note later using only
+/// their outgoing viewing key.
+pub struct EncryptedNote {
+    pub epk: SubgroupPoint,
+    pub ciphertext: Vec<u8>,
+    pub out_ciphertext: Vec<u8>,
+}
+
+/// Derives the symmetric note-encryption key from the ECDH shared-secret
+/// point and the ephemeral public key, following the Sapling KDF.
+fn kdf(shared_secret: &SubgroupPoint, epk: &SubgroupPoint) -> [u8; 32] {
+    let digest = Blake2bParams::new()
+        .hash_length(32)
+        .personal(KDF_PERSONALIZATION)
+        .to_state()
+        .update(&shared_secret.to_bytes())
+        .update(&epk.to_bytes())
+        .finalize();
+
+    let mut key = [0u8; 32];
+    key.copy_from_slice(digest.as_bytes());
+    key
+}
+
+/// Derives the key that wraps `(esk, pk_d)` for the sender's own recovery,
+/// binding it to the outgoing viewing key and the note's `epk`.
+fn derive_ock(ovk: &[u8; 32], epk: &SubgroupPoint) -> [u8; 32] {
+    let digest = Blake2bParams::new()
+        .hash_length(32)
+        .personal(OCK_PERSONALIZATION)
+        .to_state()
+        .update(ovk)
+        .update(&epk.to_bytes())
+        .finalize();
+
+    let mut key = [0u8; 32];
+    key.copy_from_slice(digest.as_bytes());
+    key
+}
+
+/// Encrypts `note` to the recipient's transmission key `pk_d`, using the
+/// sender's ephemeral key pair to perform the Diffie-Hellman exchange.
+/// `ovk` is the sender's outgoing viewing key, used to wrap `(esk, pk_d)`
+/// so the sender can later recover the note via [`try_output_recovery`].
+pub fn encrypt_note(
+    ephemeral_key_pair: &EphemeralKeyPair,
+    pk_d: SubgroupPoint,
+    ovk: &[u8; 32],
+    note: &Note,
+) -> std::io::Result<EncryptedNote> {
+    let epk = *ephemeral_key_pair.public();
+    let shared_secret = pk_d * ephemeral_key_pair.secret();
+    let key = kdf(&shared_secret, &epk);
+
+    let cipher = ChaCha20Poly1305::new(key.as_slice().into());
+
+    let mut plaintext = vec![];
+    note.write(&mut plaintext)?;
+
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(NOTE_NONCE), plaintext.as_ref())
+        .expect("note encryption should not fail");
+
+    let mut outgoing_plaintext = vec![];
+    outgoing_plaintext.extend_from_slice(&ephemeral_key_pair.secret().to_bytes());
+    outgoing_plaintext.extend_from_slice(&pk_d.to_bytes());
+
+    let ock = derive_ock(ovk, &epk);
+    let out_ciphertext = ChaCha20Poly1305::new(ock.as_slice().into())
+        .encrypt(Nonce::from_slice(OUTGOING_NONCE), outgoing_plaintext.as_ref())
+        .expect("outgoing ciphertext encryption should not fail");
+
+    Ok(EncryptedNote {
+        epk,
+        ciphertext,
+        out_ciphertext,
+    })
+}
+
+/// The receiver's trial-decryption path: recovers the shared secret from
+/// their incoming viewing key `ivk` and the carried `epk`, then opens the
+/// AEAD ciphertext. Returns `None` if `ivk` does not match this note.
+pub fn try_note_decryption(ivk: jubjub::Fr, encrypted_note: &EncryptedNote) -> Option<Note> {
+    let shared_secret = encrypted_note.epk * ivk;
+    let key = kdf(&shared_secret, &encrypted_note.epk);
+
+    let plaintext = ChaCha20Poly1305::new(key.as_slice().into())
+        .decrypt(
+            Nonce::from_slice(NOTE_NONCE),
+            encrypted_note.ciphertext.as_ref(),
+        )
+        .ok()?;
+
+    Note::read(&plaintext[..]).ok()
+}
+
+/// The sender's recovery path: using the outgoing viewing key `ovk` (which
+/// keyed the wrapping of `(esk, pk_d)` when the note was encrypted), unwraps
+/// `(esk, pk_d)` and recomputes the shared secret as `esk · pk_d`, the same
+/// point the recipient reaches as `ivk · epk`, letting the sender recover
+/// notes they sent without storing per-note ephemeral secrets.
+pub fn try_output_recovery(ovk: &[u8; 32], encrypted_note: &EncryptedNote) -> Option<Note> {
+    let ock = derive_ock(ovk, &encrypted_note.epk);
+
+    let outgoing_plaintext = ChaCha20Poly1305::new(ock.as_slice().into())
+        .decrypt(
+            Nonce::from_slice(OUTGOING_NONCE),
+            encrypted_note.out_ciphertext.as_ref(),
+        )
+        .ok()?;
+
+    let esk = jubjub::Fr::from_bytes(outgoing_plaintext[0..32].try_into().ok()?);
+    let pk_d = SubgroupPoint::from_bytes(outgoing_plaintext[32..64].try_into().ok()?);
+    if esk.is_none().into() || pk_d.is_none().into() {
+        return None;
+    }
+
+    let shared_secret = pk_d.unwrap() * esk.unwrap();
+    let key = kdf(&shared_secret, &encrypted_note.epk);
+
+    let plaintext = ChaCha20Poly1305::new(key.as_slice().into())
+        .decrypt(
+            Nonce::from_slice(NOTE_NONCE),
+            encrypted_note.ciphertext.as_ref(),
+        )
+        .ok()?;
+
+    Note::read(&plaintext[..]).ok()
+}
@@ -4,18 +4,22 @@ use std::{
 };
 
 use bellperson::{
-    gadgets::{blake2s, boolean},
+    gadgets::{blake2s, boolean, multipack},
     Circuit,
 };
 use byteorder::{ReadBytesExt, WriteBytesExt};
 use ff::PrimeField;
+use group::Curve;
 use zcash_primitives::sapling::ProofGenerationKey;
 use zcash_proofs::{
     circuit::ecc,
     constants::{PROOF_GENERATION_KEY_GENERATOR, SPENDING_KEY_GENERATOR},
 };
 
-use crate::constants::{proof::PUBLIC_KEY_GENERATOR, CRH_IVK_PERSONALIZATION};
+use crate::{
+    constants::{proof::PUBLIC_KEY_GENERATOR, CRH_IVK_PERSONALIZATION},
+    errors::DeserializeError,
+};
 use serde::{de::Visitor, Deserialize, Deserializer, Serialize, Serializer};
 
 pub struct MintAsset {
@@ -25,6 +29,13 @@ pub struct MintAsset {
     /// Used to add randomness to signature generation without leaking the
     /// key. Referred to as `ar` in the literature.
     pub public_key_randomness: Option<jubjub::Fr>,
+
+    /// When set, the owner public address is inputized as multipacked
+    /// scalars (see [`compute_multipacked_inputs`]) instead of as its raw
+    /// `u`/`v` coordinates. Gated behind this flag so proofs generated
+    /// before this circuit variant existed remain verifiable against the
+    /// un-packed input vector.
+    pub multipack_inputs: bool,
 }
 
 impl MintAsset {
@@ -41,10 +52,11 @@ impl MintAsset {
         } else {
             writer.write_u8(0)?;
         }
+        writer.write_u8(self.multipack_inputs as u8)?;
         Ok(())
     }
 
-    pub fn read<R: Read>(mut reader: R) -> std::io::Result<MintAsset> {
+    pub fn read<R: Read>(mut reader: R) -> Result<MintAsset, DeserializeError> {
         let mut proof_generation_key = None;
         if reader.read_u8()? == 1 {
             proof_generation_key = Some(ProofGenerationKey::read(&mut reader)?);
@@ -53,15 +65,49 @@ impl MintAsset {
         if reader.read_u8()? == 1 {
             let mut bytes = [0u8; 32];
             reader.read_exact(&mut bytes)?;
-            public_key_randomness = Some(jubjub::Fr::from_bytes(&bytes).unwrap());
+            public_key_randomness = Some(
+                Option::from(jubjub::Fr::from_bytes(&bytes)).ok_or(DeserializeError::InvalidScalar)?,
+            );
         }
+        let multipack_inputs = reader.read_u8()? == 1;
         Ok(MintAsset {
             proof_generation_key,
             public_key_randomness,
+            multipack_inputs,
         })
     }
 }
 
+/// Host-side equivalent of the circuit's `EdwardsPoint::repr()`: the
+/// v-coordinate bits, strictly truncated to `NUM_BITS` (i.e. no trailing
+/// padding bit from the field element's byte representation), followed by
+/// the sign bit of u. Shared by `compute_multipacked_inputs` here and by
+/// the `Output`/`Spend` circuit tests that need to reproduce the same
+/// point encoding outside a constraint system.
+pub(crate) fn edwards_point_repr_bits(point: jubjub::SubgroupPoint) -> Vec<bool> {
+    let affine = jubjub::ExtendedPoint::from(point).to_affine();
+
+    let mut bits: Vec<bool> = affine
+        .get_v()
+        .to_le_bits()
+        .iter()
+        .map(|b| *b)
+        .take(<jubjub::Fq as PrimeField>::NUM_BITS as usize)
+        .collect();
+    bits.push(affine.get_u().is_odd().into());
+
+    bits
+}
+
+/// Bit-decomposes `pk_d` the same way the circuit does when
+/// `multipack_inputs` is set, and re-packs the bits into the minimum
+/// number of scalars that fit the BLS12-381 scalar capacity. Verifiers
+/// must build the public input vector using this function so it matches
+/// the circuit's packed `owner public address` input exactly.
+pub fn compute_multipacked_inputs(pk_d: jubjub::SubgroupPoint) -> Vec<blstrs::Scalar> {
+    multipack::compute_multipacking(&edwards_point_repr_bits(pk_d))
+}
+
 impl Serialize for MintAsset {
     fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
         let mut v = Vec::new();
@@ -87,8 +133,7 @@ fn deserialize_output<'de, D: Deserializer<'de>>(d: D) -> Result<MintAsset, D::E
         }
         #[inline]
         fn visit_bytes<F: serde::de::Error>(self, v: &[u8]) -> Result<Self::Value, F> {
-            let p = MintAsset::read(v).unwrap();
-            Ok(p)
+            MintAsset::read(v).map_err(|e| F::custom(e.to_string()))
         }
     }
     d.deserialize_bytes(BytesVisitor)
@@ -183,7 +228,19 @@ impl Circuit<blstrs::Scalar> for MintAsset {
             &ivk,
         )?;
 
-        owner_public_address.inputize(cs.namespace(|| "owner public address"))?;
+        if self.multipack_inputs {
+            // Pack the owner public address into the minimum number of
+            // scalars instead of inputizing its raw u/v coordinates.
+            let mut bits = vec![];
+            bits.extend(owner_public_address.repr(cs.namespace(|| "repr of owner public address"))?);
+
+            multipack::pack_into_inputs(
+                cs.namespace(|| "pack owner public address"),
+                &bits,
+            )?;
+        } else {
+            owner_public_address.inputize(cs.namespace(|| "owner public address"))?;
+        }
 
         Ok(())
     }
@@ -232,6 +289,7 @@ mod test {
         let circuit = MintAsset {
             proof_generation_key: Some(proof_generation_key),
             public_key_randomness: Some(public_key_randomness),
+            multipack_inputs: false,
         };
 
         let mut writer = vec![];
@@ -259,4 +317,36 @@ mod test {
         // Sanity check
         assert!(cs.verify(&public_inputs));
     }
+
+    #[test]
+    fn test_mint_asset_circuit_multipack_inputs() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let mut cs = TestConstraintSystem::new();
+
+        let proof_generation_key = ProofGenerationKey {
+            ak: jubjub::SubgroupPoint::random(&mut rng),
+            nsk: jubjub::Fr::random(&mut rng),
+        };
+        let incoming_view_key = proof_generation_key.to_viewing_key();
+        let public_address = *PUBLIC_KEY_GENERATOR * incoming_view_key.ivk().0;
+
+        let public_key_randomness = jubjub::Fr::random(&mut rng);
+        let randomized_public_key =
+            ExtendedPoint::from(incoming_view_key.rk(public_key_randomness)).to_affine();
+
+        let mut public_inputs = vec![randomized_public_key.get_u(), randomized_public_key.get_v()];
+        public_inputs.extend(super::compute_multipacked_inputs(public_address));
+
+        let circuit = MintAsset {
+            proof_generation_key: Some(proof_generation_key),
+            public_key_randomness: Some(public_key_randomness),
+            multipack_inputs: true,
+        };
+
+        circuit.synthesize(&mut cs).unwrap();
+
+        assert!(cs.is_satisfied());
+        assert!(cs.verify(&public_inputs));
+    }
 }
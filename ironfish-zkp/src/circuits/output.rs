@@ -0,0 +1,313 @@
+use std::io::{self, Read, Write};
+
+use bellperson::{gadgets::boolean, Circuit};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use zcash_primitives::sapling::pedersen_hash;
+use zcash_proofs::{
+    circuit::{ecc, pedersen_hash as pedersen_hash_circuit},
+    constants::{
+        NOTE_COMMITMENT_RANDOMNESS_GENERATOR, VALUE_COMMITMENT_RANDOMNESS_GENERATOR,
+        VALUE_COMMITMENT_VALUE_GENERATOR,
+    },
+};
+
+use crate::errors::DeserializeError;
+use serde::{de::Visitor, Deserialize, Deserializer, Serialize, Serializer};
+
+/// The opening of a homomorphic value commitment: the value being
+/// committed to, together with the blinding randomness.
+pub struct ValueCommitmentOpening {
+    /// The value in the note, in the smallest denomination.
+    pub value: u64,
+
+    /// Blinding factor for the value commitment.
+    pub randomness: jubjub::Fr,
+}
+
+/// Circuit for proving that a note of some value is being created for a
+/// particular recipient, without revealing the value or the recipient.
+pub struct Output {
+    /// The opening of the value commitment being proven over.
+    pub value_commitment_opening: Option<ValueCommitmentOpening>,
+
+    /// The diversified base of the recipient's address.
+    pub g_d: Option<jubjub::SubgroupPoint>,
+
+    /// The diversified transmission key of the recipient's address.
+    pub pk_d: Option<jubjub::SubgroupPoint>,
+
+    /// Blinding factor for the note commitment.
+    pub commitment_randomness: Option<jubjub::Fr>,
+}
+
+impl Output {
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        if let Some(value_commitment_opening) = &self.value_commitment_opening {
+            writer.write_u8(1)?;
+            writer.write_u64::<LittleEndian>(value_commitment_opening.value)?;
+            writer.write_all(&value_commitment_opening.randomness.to_bytes())?;
+        } else {
+            writer.write_u8(0)?;
+        }
+
+        if let Some(g_d) = &self.g_d {
+            writer.write_u8(1)?;
+            writer.write_all(&g_d.to_bytes())?;
+        } else {
+            writer.write_u8(0)?;
+        }
+
+        if let Some(pk_d) = &self.pk_d {
+            writer.write_u8(1)?;
+            writer.write_all(&pk_d.to_bytes())?;
+        } else {
+            writer.write_u8(0)?;
+        }
+
+        if let Some(commitment_randomness) = &self.commitment_randomness {
+            writer.write_u8(1)?;
+            writer.write_all(&commitment_randomness.to_bytes())?;
+        } else {
+            writer.write_u8(0)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> Result<Output, DeserializeError> {
+        let mut value_commitment_opening = None;
+        if reader.read_u8()? == 1 {
+            let value = reader.read_u64::<LittleEndian>()?;
+            let mut randomness_bytes = [0u8; 32];
+            reader.read_exact(&mut randomness_bytes)?;
+            let randomness = Option::from(jubjub::Fr::from_bytes(&randomness_bytes))
+                .ok_or(DeserializeError::InvalidScalar)?;
+            value_commitment_opening = Some(ValueCommitmentOpening { value, randomness });
+        }
+
+        let mut g_d = None;
+        if reader.read_u8()? == 1 {
+            let mut bytes = [0u8; 32];
+            reader.read_exact(&mut bytes)?;
+            g_d = Some(
+                Option::from(jubjub::SubgroupPoint::from_bytes(&bytes))
+                    .ok_or(DeserializeError::InvalidPoint)?,
+            );
+        }
+
+        let mut pk_d = None;
+        if reader.read_u8()? == 1 {
+            let mut bytes = [0u8; 32];
+            reader.read_exact(&mut bytes)?;
+            pk_d = Some(
+                Option::from(jubjub::SubgroupPoint::from_bytes(&bytes))
+                    .ok_or(DeserializeError::InvalidPoint)?,
+            );
+        }
+
+        let mut commitment_randomness = None;
+        if reader.read_u8()? == 1 {
+            let mut bytes = [0u8; 32];
+            reader.read_exact(&mut bytes)?;
+            commitment_randomness = Some(
+                Option::from(jubjub::Fr::from_bytes(&bytes))
+                    .ok_or(DeserializeError::InvalidScalar)?,
+            );
+        }
+
+        Ok(Output {
+            value_commitment_opening,
+            g_d,
+            pk_d,
+            commitment_randomness,
+        })
+    }
+}
+
+impl Serialize for Output {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut v = Vec::new();
+        self.write(&mut v).unwrap();
+        s.serialize_bytes(&v)
+    }
+}
+
+impl<'de> Deserialize<'de> for Output {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        deserialize_output(d)
+    }
+}
+
+fn deserialize_output<'de, D: Deserializer<'de>>(d: D) -> Result<Output, D::Error> {
+    struct BytesVisitor;
+
+    impl<'de> Visitor<'de> for BytesVisitor {
+        type Value = Output;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "a proof")
+        }
+        #[inline]
+        fn visit_bytes<F: serde::de::Error>(self, v: &[u8]) -> Result<Self::Value, F> {
+            Output::read(v).map_err(|e| F::custom(e.to_string()))
+        }
+    }
+    d.deserialize_bytes(BytesVisitor)
+}
+
+impl Circuit<blstrs::Scalar> for Output {
+    fn synthesize<CS: bellperson::ConstraintSystem<blstrs::Scalar>>(
+        self,
+        cs: &mut CS,
+    ) -> Result<(), bellperson::SynthesisError> {
+        // Witness the value in the note
+        let value_bits = boolean::u64_into_boolean_vec_le(
+            cs.namespace(|| "value"),
+            self.value_commitment_opening.as_ref().map(|o| o.value),
+        )?;
+
+        // Compute the value part of the commitment
+        let value_part = ecc::fixed_base_multiplication(
+            cs.namespace(|| "computation of value commitment value part"),
+            &VALUE_COMMITMENT_VALUE_GENERATOR,
+            &value_bits,
+        )?;
+
+        // Witness the randomness for the value commitment
+        let rcv = boolean::field_into_boolean_vec_le(
+            cs.namespace(|| "rcv"),
+            self.value_commitment_opening.as_ref().map(|o| o.randomness),
+        )?;
+
+        // Compute the randomness part of the commitment
+        let rcv_part = ecc::fixed_base_multiplication(
+            cs.namespace(|| "computation of value commitment randomness part"),
+            &VALUE_COMMITMENT_RANDOMNESS_GENERATOR,
+            &rcv,
+        )?;
+
+        // Compute the value commitment and expose it as an input
+        let cv = value_part.add(cs.namespace(|| "computation of cv"), &rcv_part)?;
+        cv.inputize(cs.namespace(|| "cv"))?;
+
+        // Witness the diversified base of the recipient's address
+        let g_d = ecc::EdwardsPoint::witness(cs.namespace(|| "g_d"), self.g_d.map(Into::into))?;
+        g_d.assert_not_small_order(cs.namespace(|| "g_d not small order"))?;
+
+        // Witness the diversified transmission key of the recipient's address
+        let pk_d = ecc::EdwardsPoint::witness(cs.namespace(|| "pk_d"), self.pk_d.map(Into::into))?;
+        pk_d.assert_not_small_order(cs.namespace(|| "pk_d not small order"))?;
+
+        // Assemble the note commitment preimage: value || g_d || pk_d
+        let mut note_contents = value_bits;
+        note_contents.extend(g_d.repr(cs.namespace(|| "representation of g_d"))?);
+        note_contents.extend(pk_d.repr(cs.namespace(|| "representation of pk_d"))?);
+
+        // Compute the Pedersen hash of the note contents
+        let cm = pedersen_hash_circuit::pedersen_hash(
+            cs.namespace(|| "note content hash"),
+            pedersen_hash::Personalization::NoteCommitment,
+            &note_contents,
+        )?;
+
+        // Rerandomize the note commitment
+        let rcm = boolean::field_into_boolean_vec_le(
+            cs.namespace(|| "rcm"),
+            self.commitment_randomness,
+        )?;
+
+        let rcm_part = ecc::fixed_base_multiplication(
+            cs.namespace(|| "computation of commitment randomness part"),
+            &NOTE_COMMITMENT_RANDOMNESS_GENERATOR,
+            &rcm,
+        )?;
+
+        let cm = cm.add(cs.namespace(|| "randomization of note commitment"), &rcm_part)?;
+
+        // Only the u-coordinate of the note commitment is exposed, matching
+        // the canonical Sapling convention.
+        cm.get_u().inputize(cs.namespace(|| "commitment"))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bellperson::{gadgets::test::TestConstraintSystem, Circuit};
+    use ff::Field;
+    use group::{Curve, Group};
+    use jubjub::ExtendedPoint;
+    use rand::{rngs::StdRng, SeedableRng};
+    use zcash_primitives::sapling::pedersen_hash;
+
+    use crate::constants::{
+        NOTE_COMMITMENT_RANDOMNESS_GENERATOR, VALUE_COMMITMENT_RANDOMNESS_GENERATOR,
+        VALUE_COMMITMENT_VALUE_GENERATOR,
+    };
+
+    use super::super::mint_asset::edwards_point_repr_bits as repr_bits;
+    use super::{Output, ValueCommitmentOpening};
+
+    #[test]
+    fn test_output_circuit() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let mut cs = TestConstraintSystem::new();
+
+        let value: u64 = 42;
+        let value_randomness = jubjub::Fr::random(&mut rng);
+        let g_d = jubjub::SubgroupPoint::random(&mut rng);
+        let pk_d = jubjub::SubgroupPoint::random(&mut rng);
+        let commitment_randomness = jubjub::Fr::random(&mut rng);
+
+        // cv = value·VALUE_COMMITMENT_VALUE_GENERATOR + randomness·VALUE_COMMITMENT_RANDOMNESS_GENERATOR
+        let cv = ExtendedPoint::from(
+            *VALUE_COMMITMENT_VALUE_GENERATOR * jubjub::Fr::from(value)
+                + *VALUE_COMMITMENT_RANDOMNESS_GENERATOR * value_randomness,
+        )
+        .to_affine();
+
+        // cm = Pedersen(value || g_d || pk_d) + randomness·NOTE_COMMITMENT_RANDOMNESS_GENERATOR
+        let mut note_contents = vec![];
+        for i in 0..64 {
+            note_contents.push((value >> i) & 1 == 1);
+        }
+        note_contents.extend(repr_bits(g_d));
+        note_contents.extend(repr_bits(pk_d));
+
+        let cm = ExtendedPoint::from(
+            pedersen_hash::pedersen_hash(pedersen_hash::Personalization::NoteCommitment, note_contents)
+                + *NOTE_COMMITMENT_RANDOMNESS_GENERATOR * commitment_randomness,
+        )
+        .to_affine();
+
+        let public_inputs = vec![cv.get_u(), cv.get_v(), cm.get_u()];
+
+        let circuit = Output {
+            value_commitment_opening: Some(ValueCommitmentOpening {
+                value,
+                randomness: value_randomness,
+            }),
+            g_d: Some(g_d),
+            pk_d: Some(pk_d),
+            commitment_randomness: Some(commitment_randomness),
+        };
+
+        let mut writer = vec![];
+        circuit.write(&mut writer).unwrap();
+        Output::read(&writer[..]).unwrap();
+
+        circuit.synthesize(&mut cs).unwrap();
+
+        assert!(cs.is_satisfied());
+        assert!(cs.verify(&public_inputs));
+
+        // Bad note commitment
+        let bad_cm = ExtendedPoint::random(&mut rng).to_affine();
+        let mut bad_inputs = public_inputs.clone();
+        bad_inputs[2] = bad_cm.get_u();
+
+        assert!(!cs.verify(&bad_inputs));
+    }
+}
@@ -0,0 +1,567 @@
+use std::io::{self, Read, Write};
+
+use bellperson::{
+    gadgets::{blake2s, boolean, multipack, num::AllocatedNum},
+    Circuit,
+};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use ff::PrimeField;
+use zcash_primitives::sapling::{pedersen_hash, ProofGenerationKey};
+use zcash_proofs::{
+    circuit::{ecc, pedersen_hash as pedersen_hash_circuit},
+    constants::{
+        NOTE_COMMITMENT_RANDOMNESS_GENERATOR, NULLIFIER_POSITION_GENERATOR,
+        PROOF_GENERATION_KEY_GENERATOR, SPENDING_KEY_GENERATOR, VALUE_COMMITMENT_RANDOMNESS_GENERATOR,
+        VALUE_COMMITMENT_VALUE_GENERATOR,
+    },
+};
+
+use crate::{
+    constants::{proof::PUBLIC_KEY_GENERATOR, CRH_IVK_PERSONALIZATION, PRF_NF_PERSONALIZATION},
+    errors::DeserializeError,
+};
+use serde::{de::Visitor, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::output::ValueCommitmentOpening;
+
+/// Depth of the note commitment Merkle tree a spend proves membership in.
+pub const TREE_DEPTH: usize = 32;
+
+/// Circuit for proving that a previously-created note is being spent:
+/// that it exists in the commitment tree under `anchor`, and for
+/// revealing its nullifier so it can't be spent again, without revealing
+/// which note in the tree is being spent.
+pub struct Spend {
+    /// Key required to construct proofs for a particular spending key,
+    /// shared with [`super::mint_asset::MintAsset`].
+    pub proof_generation_key: Option<ProofGenerationKey>,
+
+    /// Used to rerandomize `ak` into `rk`, exactly as in `MintAsset`.
+    pub public_key_randomness: Option<jubjub::Fr>,
+
+    /// The value commitment opening for the note being spent.
+    pub value_commitment_opening: Option<ValueCommitmentOpening>,
+
+    /// The diversified base of the note's recipient address.
+    pub g_d: Option<jubjub::SubgroupPoint>,
+
+    /// The diversified transmission key of the note's recipient address.
+    pub pk_d: Option<jubjub::SubgroupPoint>,
+
+    /// Blinding factor used when the note's commitment was created.
+    pub commitment_randomness: Option<jubjub::Fr>,
+
+    /// Position of the note's commitment as a leaf in the tree, used to
+    /// derive the nullifier.
+    pub position: Option<u64>,
+
+    /// The Merkle authentication path from the note's commitment up to
+    /// the anchor: at each level, the sibling hash and whether the
+    /// witnessed node is the right child.
+    pub auth_path: Vec<Option<(blstrs::Scalar, bool)>>,
+}
+
+impl Spend {
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        if let Some(proof_generation_key) = &self.proof_generation_key {
+            writer.write_u8(1)?;
+            writer.write_all(proof_generation_key.to_bytes_le().as_ref())?;
+        } else {
+            writer.write_u8(0)?;
+        }
+
+        if let Some(public_key_randomness) = &self.public_key_randomness {
+            writer.write_u8(1)?;
+            writer.write_all(&public_key_randomness.to_bytes())?;
+        } else {
+            writer.write_u8(0)?;
+        }
+
+        if let Some(value_commitment_opening) = &self.value_commitment_opening {
+            writer.write_u8(1)?;
+            writer.write_u64::<LittleEndian>(value_commitment_opening.value)?;
+            writer.write_all(&value_commitment_opening.randomness.to_bytes())?;
+        } else {
+            writer.write_u8(0)?;
+        }
+
+        for point in [&self.g_d, &self.pk_d] {
+            if let Some(point) = point {
+                writer.write_u8(1)?;
+                writer.write_all(&point.to_bytes())?;
+            } else {
+                writer.write_u8(0)?;
+            }
+        }
+
+        if let Some(commitment_randomness) = &self.commitment_randomness {
+            writer.write_u8(1)?;
+            writer.write_all(&commitment_randomness.to_bytes())?;
+        } else {
+            writer.write_u8(0)?;
+        }
+
+        if let Some(position) = &self.position {
+            writer.write_u8(1)?;
+            writer.write_u64::<LittleEndian>(*position)?;
+        } else {
+            writer.write_u8(0)?;
+        }
+
+        writer.write_u64::<LittleEndian>(self.auth_path.len() as u64)?;
+        for node in &self.auth_path {
+            if let Some((hash, is_right)) = node {
+                writer.write_u8(1)?;
+                writer.write_all(&hash.to_bytes_le())?;
+                writer.write_u8(*is_right as u8)?;
+            } else {
+                writer.write_u8(0)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> Result<Spend, DeserializeError> {
+        let mut proof_generation_key = None;
+        if reader.read_u8()? == 1 {
+            proof_generation_key = Some(ProofGenerationKey::read(&mut reader)?);
+        }
+
+        let mut public_key_randomness = None;
+        if reader.read_u8()? == 1 {
+            public_key_randomness = Some(read_scalar(&mut reader)?);
+        }
+
+        let mut value_commitment_opening = None;
+        if reader.read_u8()? == 1 {
+            let value = reader.read_u64::<LittleEndian>()?;
+            let randomness = read_scalar(&mut reader)?;
+            value_commitment_opening = Some(ValueCommitmentOpening { value, randomness });
+        }
+
+        let g_d = if reader.read_u8()? == 1 {
+            Some(read_subgroup_point(&mut reader)?)
+        } else {
+            None
+        };
+        let pk_d = if reader.read_u8()? == 1 {
+            Some(read_subgroup_point(&mut reader)?)
+        } else {
+            None
+        };
+
+        let mut commitment_randomness = None;
+        if reader.read_u8()? == 1 {
+            commitment_randomness = Some(read_scalar(&mut reader)?);
+        }
+
+        let mut position = None;
+        if reader.read_u8()? == 1 {
+            position = Some(reader.read_u64::<LittleEndian>()?);
+        }
+
+        let len = reader.read_u64::<LittleEndian>()? as usize;
+        let mut auth_path = Vec::with_capacity(len);
+        for _ in 0..len {
+            if reader.read_u8()? == 1 {
+                let mut hash_bytes = [0u8; 32];
+                reader.read_exact(&mut hash_bytes)?;
+                let hash = Option::from(blstrs::Scalar::from_bytes_le(&hash_bytes))
+                    .ok_or(DeserializeError::InvalidScalar)?;
+                let is_right = reader.read_u8()? == 1;
+                auth_path.push(Some((hash, is_right)));
+            } else {
+                auth_path.push(None);
+            }
+        }
+
+        Ok(Spend {
+            proof_generation_key,
+            public_key_randomness,
+            value_commitment_opening,
+            g_d,
+            pk_d,
+            commitment_randomness,
+            position,
+            auth_path,
+        })
+    }
+}
+
+fn read_scalar<R: Read>(mut reader: R) -> Result<jubjub::Fr, DeserializeError> {
+    let mut bytes = [0u8; 32];
+    reader.read_exact(&mut bytes)?;
+    Option::from(jubjub::Fr::from_bytes(&bytes)).ok_or(DeserializeError::InvalidScalar)
+}
+
+fn read_subgroup_point<R: Read>(mut reader: R) -> Result<jubjub::SubgroupPoint, DeserializeError> {
+    let mut bytes = [0u8; 32];
+    reader.read_exact(&mut bytes)?;
+    Option::from(jubjub::SubgroupPoint::from_bytes(&bytes)).ok_or(DeserializeError::InvalidPoint)
+}
+
+impl Serialize for Spend {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut v = Vec::new();
+        self.write(&mut v).unwrap();
+        s.serialize_bytes(&v)
+    }
+}
+
+impl<'de> Deserialize<'de> for Spend {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        deserialize_spend(d)
+    }
+}
+
+fn deserialize_spend<'de, D: Deserializer<'de>>(d: D) -> Result<Spend, D::Error> {
+    struct BytesVisitor;
+
+    impl<'de> Visitor<'de> for BytesVisitor {
+        type Value = Spend;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "a proof")
+        }
+        #[inline]
+        fn visit_bytes<F: serde::de::Error>(self, v: &[u8]) -> Result<Self::Value, F> {
+            Spend::read(v).map_err(|e| F::custom(e.to_string()))
+        }
+    }
+    d.deserialize_bytes(BytesVisitor)
+}
+
+impl Circuit<blstrs::Scalar> for Spend {
+    fn synthesize<CS: bellperson::ConstraintSystem<blstrs::Scalar>>(
+        self,
+        cs: &mut CS,
+    ) -> Result<(), bellperson::SynthesisError> {
+        // Prover witnesses ak (ensures that it's on the curve)
+        let ak = ecc::EdwardsPoint::witness(
+            cs.namespace(|| "ak"),
+            self.proof_generation_key.as_ref().map(|k| k.ak.into()),
+        )?;
+        ak.assert_not_small_order(cs.namespace(|| "ak not small order"))?;
+
+        // Rerandomize ak and expose it as rk, exactly as in MintAsset
+        {
+            let ar = boolean::field_into_boolean_vec_le(
+                cs.namespace(|| "ar"),
+                self.public_key_randomness,
+            )?;
+
+            let ar = ecc::fixed_base_multiplication(
+                cs.namespace(|| "computation of randomization for the signing key"),
+                &SPENDING_KEY_GENERATOR,
+                &ar,
+            )?;
+
+            let rk = ak.add(cs.namespace(|| "computation of rk"), &ar)?;
+            rk.inputize(cs.namespace(|| "rk"))?;
+        }
+
+        // Compute nk = [nsk] ProofGenerationKey
+        let nsk = boolean::field_into_boolean_vec_le(
+            cs.namespace(|| "nsk"),
+            self.proof_generation_key.as_ref().map(|k| k.nsk),
+        )?;
+        let nk = ecc::fixed_base_multiplication(
+            cs.namespace(|| "computation of nk"),
+            &PROOF_GENERATION_KEY_GENERATOR,
+            &nsk,
+        )?;
+
+        // Compute ivk = CRH^ivk(ak || nk), same derivation as MintAsset
+        let mut ivk_preimage = vec![];
+        ivk_preimage.extend(ak.repr(cs.namespace(|| "representation of ak"))?);
+        ivk_preimage.extend(nk.repr(cs.namespace(|| "representation of nk"))?);
+        assert_eq!(ivk_preimage.len(), 512);
+
+        let mut ivk = blake2s::blake2s(
+            cs.namespace(|| "computation of ivk"),
+            &ivk_preimage,
+            CRH_IVK_PERSONALIZATION,
+        )?;
+        ivk.truncate(jubjub::Fr::CAPACITY as usize);
+
+        // pk_d of the note's recipient, i.e. the owner's public address
+        let g_d = ecc::EdwardsPoint::witness(cs.namespace(|| "g_d"), self.g_d.map(Into::into))?;
+        g_d.assert_not_small_order(cs.namespace(|| "g_d not small order"))?;
+
+        let pk_d = ecc::EdwardsPoint::witness(cs.namespace(|| "pk_d"), self.pk_d.map(Into::into))?;
+        pk_d.assert_not_small_order(cs.namespace(|| "pk_d not small order"))?;
+
+        // Witness the note's value and compute the value commitment
+        let value_bits = boolean::u64_into_boolean_vec_le(
+            cs.namespace(|| "value"),
+            self.value_commitment_opening.as_ref().map(|o| o.value),
+        )?;
+
+        let value_part = ecc::fixed_base_multiplication(
+            cs.namespace(|| "computation of value commitment value part"),
+            &VALUE_COMMITMENT_VALUE_GENERATOR,
+            &value_bits,
+        )?;
+
+        let rcv = boolean::field_into_boolean_vec_le(
+            cs.namespace(|| "rcv"),
+            self.value_commitment_opening.as_ref().map(|o| o.randomness),
+        )?;
+
+        let rcv_part = ecc::fixed_base_multiplication(
+            cs.namespace(|| "computation of value commitment randomness part"),
+            &VALUE_COMMITMENT_RANDOMNESS_GENERATOR,
+            &rcv,
+        )?;
+
+        let cv = value_part.add(cs.namespace(|| "computation of cv"), &rcv_part)?;
+        cv.inputize(cs.namespace(|| "cv"))?;
+
+        // Recompute the note commitment exactly as the Output circuit does
+        let mut note_contents = value_bits;
+        note_contents.extend(g_d.repr(cs.namespace(|| "representation of g_d"))?);
+        note_contents.extend(pk_d.repr(cs.namespace(|| "representation of pk_d"))?);
+
+        let cm = pedersen_hash_circuit::pedersen_hash(
+            cs.namespace(|| "note content hash"),
+            pedersen_hash::Personalization::NoteCommitment,
+            &note_contents,
+        )?;
+
+        let rcm = boolean::field_into_boolean_vec_le(
+            cs.namespace(|| "rcm"),
+            self.commitment_randomness,
+        )?;
+        let rcm_part = ecc::fixed_base_multiplication(
+            cs.namespace(|| "computation of commitment randomness part"),
+            &NOTE_COMMITMENT_RANDOMNESS_GENERATOR,
+            &rcm,
+        )?;
+        let cm = cm.add(cs.namespace(|| "randomization of note commitment"), &rcm_part)?;
+
+        // Hash up the authentication path to the anchor
+        let mut cur = cm.get_u().clone();
+
+        for (i, node) in self.auth_path.into_iter().enumerate() {
+            let cs = &mut cs.namespace(|| format!("merkle tree hash {}", i));
+
+            let (sibling, is_right) = match node {
+                Some((sibling, is_right)) => (Some(sibling), Some(is_right)),
+                None => (None, None),
+            };
+
+            let sibling = AllocatedNum::alloc(cs.namespace(|| "sibling"), || {
+                sibling.ok_or(bellperson::SynthesisError::AssignmentMissing)
+            })?;
+
+            let is_right = boolean::Boolean::from(boolean::AllocatedBit::alloc(
+                cs.namespace(|| "is right"),
+                is_right,
+            )?);
+
+            let (left, right) = AllocatedNum::conditionally_reverse(
+                cs.namespace(|| "conditional reversal of preimage"),
+                &cur,
+                &sibling,
+                &is_right,
+            )?;
+
+            let mut preimage = vec![];
+            preimage.extend(left.to_bits_le(cs.namespace(|| "left bits"))?);
+            preimage.extend(right.to_bits_le(cs.namespace(|| "right bits"))?);
+
+            cur = pedersen_hash_circuit::pedersen_hash(
+                cs.namespace(|| "computation of pedersen hash"),
+                pedersen_hash::Personalization::MerkleTree(i),
+                &preimage,
+            )?
+            .get_u()
+            .clone();
+        }
+
+        cur.inputize(cs.namespace(|| "anchor"))?;
+
+        // Derive the nullifier: nf = BLAKE2s(nk || position·NULLIFIER_POSITION_GENERATOR + cm)
+        let position_bits = boolean::u64_into_boolean_vec_le(cs.namespace(|| "position"), self.position)?;
+
+        let position_point = ecc::fixed_base_multiplication(
+            cs.namespace(|| "computation of nullifier position"),
+            &NULLIFIER_POSITION_GENERATOR,
+            &position_bits,
+        )?;
+
+        let nf_point = position_point.add(cs.namespace(|| "nf computation"), &cm)?;
+
+        let mut nf_preimage = vec![];
+        nf_preimage.extend(nk.repr(cs.namespace(|| "representation of nk for nf"))?);
+        nf_preimage.extend(nf_point.repr(cs.namespace(|| "representation of nf point"))?);
+        assert_eq!(nf_preimage.len(), 512);
+
+        let nf = blake2s::blake2s(
+            cs.namespace(|| "computation of nf"),
+            &nf_preimage,
+            PRF_NF_PERSONALIZATION,
+        )?;
+
+        multipack::pack_into_inputs(cs.namespace(|| "pack nullifier"), &nf)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bellperson::{gadgets::test::TestConstraintSystem, Circuit};
+    use blake2s_simd::Params as Blake2sParams;
+    use ff::{Field, PrimeField};
+    use group::{Curve, Group};
+    use jubjub::ExtendedPoint;
+    use rand::{rngs::StdRng, SeedableRng};
+    use zcash_primitives::sapling::{pedersen_hash, ProofGenerationKey};
+    use zcash_proofs::constants::NULLIFIER_POSITION_GENERATOR;
+
+    use crate::constants::{
+        NOTE_COMMITMENT_RANDOMNESS_GENERATOR, PRF_NF_PERSONALIZATION,
+        VALUE_COMMITMENT_RANDOMNESS_GENERATOR, VALUE_COMMITMENT_VALUE_GENERATOR,
+    };
+
+    use super::super::mint_asset::edwards_point_repr_bits;
+    use super::{Spend, ValueCommitmentOpening, TREE_DEPTH};
+
+    /// Host-side equivalent of `field_into_boolean_vec_le`: a field
+    /// element's bits, strictly truncated to `NUM_BITS`.
+    fn scalar_bits(s: blstrs::Scalar) -> Vec<bool> {
+        s.to_le_bits()
+            .iter()
+            .map(|b| *b)
+            .take(<blstrs::Scalar as PrimeField>::NUM_BITS as usize)
+            .collect()
+    }
+
+    /// Jubjub's base field and BLS12-381's scalar field are the same
+    /// field (that's what makes Jubjub embeddable), so this is a lossless
+    /// re-encoding between the two Rust types sharing it.
+    fn fq_to_scalar(fq: jubjub::Fq) -> blstrs::Scalar {
+        Option::from(blstrs::Scalar::from_repr(fq.to_repr())).unwrap()
+    }
+
+    #[test]
+    fn test_spend_circuit() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut cs = TestConstraintSystem::new();
+
+        let proof_generation_key = ProofGenerationKey {
+            ak: jubjub::SubgroupPoint::random(&mut rng),
+            nsk: jubjub::Fr::random(&mut rng),
+        };
+        let viewing_key = proof_generation_key.to_viewing_key();
+
+        let value: u64 = 7;
+        let value_randomness = jubjub::Fr::random(&mut rng);
+        let g_d = jubjub::SubgroupPoint::random(&mut rng);
+        let pk_d = jubjub::SubgroupPoint::random(&mut rng);
+        let commitment_randomness = jubjub::Fr::random(&mut rng);
+        let public_key_randomness = jubjub::Fr::random(&mut rng);
+        let position: u64 = 0;
+
+        let auth_path: Vec<Option<(blstrs::Scalar, bool)>> = (0..TREE_DEPTH)
+            .map(|_| Some((blstrs::Scalar::random(&mut rng), false)))
+            .collect();
+
+        // rk, exactly as derived by MintAsset
+        let rk = ExtendedPoint::from(viewing_key.rk(public_key_randomness)).to_affine();
+
+        // cv = value·VALUE_COMMITMENT_VALUE_GENERATOR + randomness·VALUE_COMMITMENT_RANDOMNESS_GENERATOR
+        let cv_point = *VALUE_COMMITMENT_VALUE_GENERATOR * jubjub::Fr::from(value)
+            + *VALUE_COMMITMENT_RANDOMNESS_GENERATOR * value_randomness;
+        let cv = ExtendedPoint::from(cv_point).to_affine();
+
+        // cm = Pedersen(value || g_d || pk_d) + randomness·NOTE_COMMITMENT_RANDOMNESS_GENERATOR
+        let mut note_contents = vec![];
+        for i in 0..64 {
+            note_contents.push((value >> i) & 1 == 1);
+        }
+        note_contents.extend(edwards_point_repr_bits(g_d));
+        note_contents.extend(edwards_point_repr_bits(pk_d));
+
+        let cm_point = pedersen_hash::pedersen_hash(
+            pedersen_hash::Personalization::NoteCommitment,
+            note_contents,
+        ) + *NOTE_COMMITMENT_RANDOMNESS_GENERATOR * commitment_randomness;
+
+        // Hash up the authentication path to the anchor
+        let mut cur = fq_to_scalar(ExtendedPoint::from(cm_point).to_affine().get_u());
+        for (i, node) in auth_path.iter().enumerate() {
+            let (sibling, is_right) = node.unwrap();
+            let (left, right) = if is_right { (sibling, cur) } else { (cur, sibling) };
+
+            let mut preimage = scalar_bits(left);
+            preimage.extend(scalar_bits(right));
+
+            let hashed = pedersen_hash::pedersen_hash(
+                pedersen_hash::Personalization::MerkleTree(i),
+                preimage,
+            );
+            cur = fq_to_scalar(ExtendedPoint::from(hashed).to_affine().get_u());
+        }
+        let anchor = cur;
+
+        // nf = BLAKE2s(nk || position·NULLIFIER_POSITION_GENERATOR + cm)
+        let nf_point = *NULLIFIER_POSITION_GENERATOR * jubjub::Fr::from(position) + cm_point;
+
+        let mut nf_preimage = edwards_point_repr_bits(viewing_key.nk);
+        nf_preimage.extend(edwards_point_repr_bits(nf_point));
+
+        let mut nf_bytes = [0u8; 64];
+        for (byte, bits) in nf_bytes.iter_mut().zip(nf_preimage.chunks(8)) {
+            for (i, bit) in bits.iter().enumerate() {
+                *byte |= (*bit as u8) << i;
+            }
+        }
+        let nf_digest = Blake2sParams::new()
+            .hash_length(32)
+            .personal(PRF_NF_PERSONALIZATION)
+            .to_state()
+            .update(&nf_bytes)
+            .finalize();
+        let nf_bits: Vec<bool> = (0..256)
+            .map(|i| (nf_digest.as_bytes()[i / 8] >> (i % 8)) & 1 == 1)
+            .collect();
+
+        let mut public_inputs = vec![rk.get_u(), rk.get_v(), cv.get_u(), cv.get_v(), anchor];
+        public_inputs.extend(bellperson::gadgets::multipack::compute_multipacking(
+            &nf_bits,
+        ));
+
+        let circuit = Spend {
+            proof_generation_key: Some(proof_generation_key),
+            public_key_randomness: Some(public_key_randomness),
+            value_commitment_opening: Some(ValueCommitmentOpening {
+                value,
+                randomness: value_randomness,
+            }),
+            g_d: Some(g_d),
+            pk_d: Some(pk_d),
+            commitment_randomness: Some(commitment_randomness),
+            position: Some(position),
+            auth_path,
+        };
+
+        let mut writer = vec![];
+        circuit.write(&mut writer).unwrap();
+        Spend::read(&writer[..]).unwrap();
+
+        circuit.synthesize(&mut cs).unwrap();
+
+        assert!(cs.is_satisfied());
+        assert_eq!(cs.num_constraints(), 98841);
+        assert!(cs.verify(&public_inputs));
+
+        // Bad anchor
+        let mut bad_inputs = public_inputs.clone();
+        bad_inputs[4] = blstrs::Scalar::random(&mut rng);
+
+        assert!(!cs.verify(&bad_inputs));
+    }
+}
@@ -0,0 +1,26 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use std::io;
+
+use thiserror::Error;
+
+/// Errors produced when deserializing bytes into one of this crate's
+/// proof or key types. Every variant is recoverable: malformed or
+/// truncated input from the network should be rejected, not panic the
+/// process.
+#[derive(Error, Debug)]
+pub enum DeserializeError {
+    #[error("invalid scalar encoding")]
+    InvalidScalar,
+
+    #[error("point is not a valid canonical encoding, or is not in the prime-order subgroup")]
+    InvalidPoint,
+
+    #[error("unexpected length: expected {expected}, found {found}")]
+    UnexpectedLength { expected: usize, found: usize },
+
+    #[error("io error: {0}")]
+    IoError(#[from] io::Error),
+}
@@ -0,0 +1,193 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! RedDSA signatures over the Jubjub curve, rerandomized the same way
+//! `MintAsset::synthesize` rerandomizes `ak` into `rk`. This lets a
+//! signer prove ownership of the randomized key that a mint proof's
+//! public input already commits to, without ever revealing `ak`.
+
+use std::io::{self, Read, Write};
+
+use blake2b_simd::Params as Blake2bParams;
+use group::GroupEncoding;
+use jubjub::SubgroupPoint;
+use rand::{CryptoRng, RngCore};
+
+use zcash_proofs::constants::SPENDING_KEY_GENERATOR;
+
+const PERSONALIZATION: &[u8; 16] = b"Zcash_RedJubjubH";
+
+/// Hashes `Rbar || rk_bar || msg` with BLAKE2b-512 under the `RedJubjub`
+/// signature personalization and reduces the digest into a Jubjub scalar,
+/// giving the Fiat-Shamir challenge `c` used by both signing and
+/// verification.
+fn h_star(rbar: &[u8; 32], rk_bar: &[u8; 32], msg: &[u8]) -> jubjub::Fr {
+    let mut hasher = Blake2bParams::new()
+        .hash_length(64)
+        .personal(PERSONALIZATION)
+        .to_state();
+    hasher.update(rbar);
+    hasher.update(rk_bar);
+    hasher.update(msg);
+    jubjub::Fr::from_bytes_wide(hasher.finalize().as_array())
+}
+
+/// A RedDSA private (signing) scalar, `ask` or its rerandomization `rsk`.
+#[derive(Clone, Copy)]
+pub struct PrivateKey(pub jubjub::Fr);
+
+/// A RedDSA public key point, `ak` or its rerandomization `rk`.
+#[derive(Clone, Copy)]
+pub struct PublicKey(pub SubgroupPoint);
+
+/// A RedDSA signature, stored as the pair of 32-byte encodings `(Rbar, Sbar)`.
+#[derive(Clone, Copy, Debug)]
+pub struct Signature {
+    rbar: [u8; 32],
+    sbar: [u8; 32],
+}
+
+impl PrivateKey {
+    /// Rerandomizes this key by `ar`, mirroring the circuit's
+    /// `rsk = ask + ar`.
+    pub fn randomize(&self, ar: jubjub::Fr) -> Self {
+        PrivateKey(self.0 + ar)
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut bytes = [0u8; 32];
+        reader.read_exact(&mut bytes)?;
+        let scalar = jubjub::Fr::from_bytes(&bytes);
+        if scalar.is_some().into() {
+            Ok(PrivateKey(scalar.unwrap()))
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid RedJubjub private key scalar",
+            ))
+        }
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&self.0.to_bytes())
+    }
+
+    /// Signs `msg` with this key, sampling a fresh nonce `r` from `rng`.
+    pub fn sign<R: CryptoRng + RngCore>(&self, msg: &[u8], rng: &mut R, public_key: &PublicKey) -> Signature {
+        let r = jubjub::Fr::random(rng);
+        let r_g = SPENDING_KEY_GENERATOR * r;
+
+        let rbar = r_g.to_bytes();
+        let rk_bar = public_key.0.to_bytes();
+
+        let c = h_star(&rbar, &rk_bar, msg);
+
+        let s = r + c * self.0;
+
+        Signature {
+            rbar,
+            sbar: s.to_bytes(),
+        }
+    }
+}
+
+impl PublicKey {
+    /// Rerandomizes this key by `ar`, mirroring the circuit's
+    /// `rk = ak + ar·SPENDING_KEY_GENERATOR`.
+    pub fn randomize(&self, ar: jubjub::Fr) -> Self {
+        PublicKey(self.0 + SPENDING_KEY_GENERATOR * ar)
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut bytes = [0u8; 32];
+        reader.read_exact(&mut bytes)?;
+        let point = SubgroupPoint::from_bytes(&bytes);
+        if point.is_some().into() {
+            Ok(PublicKey(point.unwrap()))
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid RedJubjub public key point",
+            ))
+        }
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&self.0.to_bytes())
+    }
+
+    /// Verifies that `signature` was produced by the holder of the
+    /// (possibly rerandomized) private key matching this public key.
+    pub fn verify(&self, msg: &[u8], signature: &Signature) -> bool {
+        let r_g = SubgroupPoint::from_bytes(&signature.rbar);
+        let s = jubjub::Fr::from_bytes(&signature.sbar);
+        if r_g.is_none().into() || s.is_none().into() {
+            return false;
+        }
+        let r_g = r_g.unwrap();
+        let s = s.unwrap();
+
+        let rk_bar = self.0.to_bytes();
+        let c = h_star(&signature.rbar, &rk_bar, msg);
+
+        // S·SPENDING_KEY_GENERATOR == R + c·rk
+        SPENDING_KEY_GENERATOR * s == r_g + self.0 * c
+    }
+}
+
+impl Signature {
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut rbar = [0u8; 32];
+        let mut sbar = [0u8; 32];
+        reader.read_exact(&mut rbar)?;
+        reader.read_exact(&mut sbar)?;
+        Ok(Signature { rbar, sbar })
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&self.rbar)?;
+        writer.write_all(&self.sbar)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ff::Field;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use zcash_proofs::constants::SPENDING_KEY_GENERATOR;
+
+    use super::{PrivateKey, PublicKey};
+
+    #[test]
+    fn test_sign_verify() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let sk = PrivateKey(jubjub::Fr::random(&mut rng));
+        let pk = PublicKey(SPENDING_KEY_GENERATOR * sk.0);
+
+        let msg = b"iron fish mint";
+        let sig = sk.sign(msg, &mut rng, &pk);
+
+        assert!(pk.verify(msg, &sig));
+        assert!(!pk.verify(b"different message", &sig));
+    }
+
+    #[test]
+    fn test_randomized_key_matches_circuit_relation() {
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let ask = PrivateKey(jubjub::Fr::random(&mut rng));
+        let ak = PublicKey(SPENDING_KEY_GENERATOR * ask.0);
+
+        let ar = jubjub::Fr::random(&mut rng);
+        let rsk = ask.randomize(ar);
+        let rk = ak.randomize(ar);
+
+        let msg = b"randomized mint signature";
+        let sig = rsk.sign(msg, &mut rng, &rk);
+
+        assert!(rk.verify(msg, &sig));
+    }
+}